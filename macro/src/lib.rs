@@ -5,9 +5,13 @@ use std::{
     string::FromUtf8Error,
 };
 
-use proc_macro2::{Span, TokenStream};
+use proc_macro2::{Literal, Span, TokenStream};
 use quote::quote;
-use syn::{parse_macro_input, LitStr};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, Attribute, Data, DeriveInput, Fields, GenericArgument, Ident, LitStr,
+    PathArguments, Token, Type,
+};
 
 #[proc_macro]
 pub fn embed_config_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -17,12 +21,60 @@ pub fn embed_config_value(input: proc_macro::TokenStream) -> proc_macro::TokenSt
         .into()
 }
 
+/// `embed_config_value_as!("path", u8)`: like `embed_config_value!`, but
+/// narrows the resolved value to the given scalar type at macro-expansion
+/// time instead of always emitting the widest native type, so callers on
+/// constrained targets don't need a runtime cast (or an `#[allow]` for the
+/// truncation lint that cast would otherwise warrant).
+struct EmbedConfigValueAs {
+    name: LitStr,
+    ty: Type,
+}
+
+impl Parse for EmbedConfigValueAs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let ty = input.parse()?;
+        Ok(Self { name, ty })
+    }
+}
+
+#[proc_macro]
+pub fn embed_config_value_as(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input as EmbedConfigValueAs);
+    embed_config_value_as_impl(args)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Resolves a user struct's fields from the embedded config file at compile
+/// time and exposes them as a `pub const EMBEDDED: Self`. A field whose type
+/// isn't one of the recognized scalars is assumed to itself
+/// `#[derive(EmbedConfig)]`, and is resolved by referencing that type's own
+/// `EMBEDDED` constant rather than by reflecting into its fields here — so
+/// it's the nested type's own `#[embed_config(path = "...")]` attribute,
+/// not this one, that decides where in the config tree it's read from.
+/// `Option<T>` is only supported for scalar `T`.
+#[proc_macro_derive(EmbedConfig, attributes(embed_config))]
+pub fn derive_embed_config(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive_embed_config_impl(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 #[derive(Debug)]
 enum ConfigError {
     NotExist(PathBuf),
     LoadError(PathBuf, std::io::Error),
     InvalidEncoding(String),
     MissingField(String),
+    UnknownFormat(PathBuf),
+    Parser {
+        format: &'static str,
+        message: String,
+    },
 }
 
 impl Display for ConfigError {
@@ -36,6 +88,14 @@ impl Display for ConfigError {
             ),
             Self::InvalidEncoding(e) => write!(f, "loading {e} lead to a decode error"),
             Self::MissingField(mf) => write!(f, "config does not contain a field matching {mf}"),
+            Self::UnknownFormat(p) => write!(
+                f,
+                "cannot determine the config format of {} from its extension; set package.metadata.embedded-config.format explicitly",
+                p.to_string_lossy()
+            ),
+            Self::Parser { format, message } => {
+                write!(f, "not valid {format}: {message}")
+            }
         }
     }
 }
@@ -60,9 +120,79 @@ impl From<FromUtf8Error> for ConfigError {
     }
 }
 
-impl From<toml::de::Error> for ConfigError {
-    fn from(err: toml::de::Error) -> Self {
-        Self::InvalidEncoding(format!("not valid toml: {err}"))
+/// The config source formats `Config` knows how to parse, one per supported
+/// backend. TOML is always available; the others are gated behind a Cargo
+/// feature of the same name so MCU builds only pull in the parser they need.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Toml,
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "ron")]
+    Ron,
+}
+
+impl Format {
+    fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "toml" => Some(Self::Toml),
+            #[cfg(feature = "json")]
+            "json" => Some(Self::Json),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Self::Yaml),
+            #[cfg(feature = "ron")]
+            "ron" => Some(Self::Ron),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "toml" => Some(Self::Toml),
+            #[cfg(feature = "json")]
+            "json" => Some(Self::Json),
+            #[cfg(feature = "yaml")]
+            "yaml" => Some(Self::Yaml),
+            #[cfg(feature = "ron")]
+            "ron" => Some(Self::Ron),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            #[cfg(feature = "json")]
+            Self::Json => "json",
+            #[cfg(feature = "yaml")]
+            Self::Yaml => "yaml",
+            #[cfg(feature = "ron")]
+            Self::Ron => "ron",
+        }
+    }
+}
+
+fn parse_value(format: Format, content: &str) -> Result<toml::Value, ConfigError> {
+    let to_parser_error = |message: String| ConfigError::Parser {
+        format: format.name(),
+        message,
+    };
+    match format {
+        Format::Toml => toml::from_str(content).map_err(|e| to_parser_error(e.to_string())),
+        #[cfg(feature = "json")]
+        Format::Json => {
+            serde_json::from_str::<toml::Value>(content).map_err(|e| to_parser_error(e.to_string()))
+        }
+        #[cfg(feature = "yaml")]
+        Format::Yaml => {
+            serde_yaml::from_str::<toml::Value>(content).map_err(|e| to_parser_error(e.to_string()))
+        }
+        #[cfg(feature = "ron")]
+        Format::Ron => {
+            ron::from_str::<toml::Value>(content).map_err(|e| to_parser_error(e.to_string()))
+        }
     }
 }
 
@@ -104,59 +234,665 @@ struct Config {
 
 impl Config {
     pub fn from_file<P: Into<PathBuf> + AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let format = Format::from_extension(
+            path.as_ref()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or(""),
+        )
+        .ok_or_else(|| ConfigError::UnknownFormat(path.as_ref().to_path_buf()))?;
+        Self::from_file_with_format(path, format)
+    }
+
+    fn from_file_with_format<P: Into<PathBuf> + AsRef<Path>>(
+        path: P,
+        format: Format,
+    ) -> Result<Self, ConfigError> {
         let content = String::from_utf8(
             std::fs::read(path.as_ref()).map_err(|e| ConfigError::from_io_error(path, e))?,
         )?;
         Ok(Self {
-            root: toml::from_str(&content)?,
+            root: parse_value(format, &content)?,
         })
     }
 
     pub fn resolve_field(&self, name: &str) -> Result<toml::Value, ConfigError> {
-        name.split('.')
+        let resolved = name
+            .split('.')
             .try_fold(&self.root, |cfg, f| {
                 cfg.get(f).ok_or(ConfigError::MissingField(name.into()))
             })
-            .cloned()
+            .cloned();
+        match resolved {
+            Ok(value) => apply_env_override(name, Some(value))
+                .map(|v| v.expect("override preserves a present value")),
+            Err(ConfigError::MissingField(_)) => apply_env_override(name, None)
+                .and_then(|v| v.ok_or_else(|| ConfigError::MissingField(name.into()))),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deep-merges `other` into `self`, leaf by leaf: tables are merged
+    /// key-by-key, everything else (including arrays) is replaced wholesale
+    /// by the value from `other`. Used to layer config sources loaded in
+    /// order, each later layer overriding the earlier ones.
+    fn merge(&mut self, other: Config) {
+        merge_values(&mut self.root, other.root);
+    }
+}
+
+fn merge_values(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Env var name an individual field can be overridden with, mangled as
+/// `EMBEDDED_CONFIG__<path with '.' replaced by '__'>`.
+fn env_override_name(field_path: &str) -> String {
+    format!("EMBEDDED_CONFIG__{}", field_path.replace('.', "__"))
+}
+
+/// Applies a build-time environment override for `name`, if one is set.
+/// When `current` holds a boolean, integer or float, the override is parsed
+/// as that same TOML type so callers keep seeing a consistently typed
+/// `Value`; any other present value (string, array, table, datetime) is
+/// replaced by the raw string. When the field is entirely absent from the
+/// config file the override is injected as a string, enabling secrets to be
+/// supplied purely from the environment.
+fn apply_env_override(
+    name: &str,
+    current: Option<toml::Value>,
+) -> Result<Option<toml::Value>, ConfigError> {
+    let var_name = env_override_name(name);
+    let Ok(raw) = env::var(&var_name) else {
+        return Ok(current);
+    };
+    fn invalid(var_name: &str, expected: &str, err: impl Display) -> ConfigError {
+        ConfigError::InvalidEncoding(format!("{var_name} is not a valid {expected}: {err}"))
+    }
+    let value = match current {
+        None => toml::Value::String(raw),
+        Some(toml::Value::Boolean(_)) => {
+            toml::Value::Boolean(raw.parse().map_err(|e| invalid(&var_name, "bool", e))?)
+        }
+        Some(toml::Value::Integer(_)) => {
+            toml::Value::Integer(raw.parse().map_err(|e| invalid(&var_name, "integer", e))?)
+        }
+        Some(toml::Value::Float(_)) => {
+            toml::Value::Float(raw.parse().map_err(|e| invalid(&var_name, "float", e))?)
+        }
+        Some(_) => toml::Value::String(raw),
+    };
+    Ok(Some(value))
+}
+
+/// Looks up `package.metadata.embedded-config.format` in the manifest, if
+/// present, so callers can override extension sniffing for config files
+/// whose name doesn't carry a recognized suffix.
+fn resolve_format_override(manifest: &Config) -> Result<Option<Format>, Error> {
+    match manifest.resolve_field("package.metadata.embedded-config.format") {
+        Ok(toml::Value::String(name)) => Format::from_name(&name)
+            .map(Some)
+            .ok_or(Error::InvalidConfigValue),
+        Ok(_) => Err(Error::InvalidConfigValue),
+        Err(ConfigError::MissingField(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `package.metadata.embedded-config.path` accepts either a single path or
+/// an array of paths loaded as layers, later ones overriding earlier ones.
+fn resolve_layer_paths(value: toml::Value) -> Result<Vec<String>, Error> {
+    match value {
+        toml::Value::String(s) => Ok(vec![s]),
+        toml::Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                toml::Value::String(s) => Ok(s),
+                _ => Err(Error::InvalidConfigValue),
+            })
+            .collect(),
+        _ => Err(Error::InvalidConfigValue),
+    }
+}
+
+fn load_layer<P: Into<PathBuf> + AsRef<Path>>(
+    path: P,
+    format: Option<Format>,
+) -> Result<Config, ConfigError> {
+    match format {
+        Some(format) => Config::from_file_with_format(path, format),
+        None => Config::from_file(path),
     }
 }
 
 fn load_embed_config() -> Result<Config, Error> {
     env::var("EMBEDDED_CONFIG_PATH")
-        .map(PathBuf::from)
+        .map(|p| vec![(PathBuf::from(p), None)])
         .or_else(|_| {
-            let mut manifest_dir = env::var("CARGO_MANIFEST_DIR").map(PathBuf::from)?;
-            let config = {
+            let manifest_dir = env::var("CARGO_MANIFEST_DIR").map(PathBuf::from)?;
+            let manifest = {
                 let mut path = manifest_dir.clone();
                 path.push("Cargo.toml");
                 Config::from_file(path)
             }?;
-            let toml::Value::String(s) =
-                config.resolve_field("package.metadata.embedded-config.path")?
-            else {
-                return Err(Error::InvalidConfigValue);
-            };
-            manifest_dir.push(s);
-            Ok(manifest_dir)
+            let paths = resolve_layer_paths(
+                manifest.resolve_field("package.metadata.embedded-config.path")?,
+            )?;
+            let format = resolve_format_override(&manifest)?;
+            Ok(paths
+                .into_iter()
+                .map(|p| {
+                    let mut layer_path = manifest_dir.clone();
+                    layer_path.push(p);
+                    (layer_path, format)
+                })
+                .collect())
+        })
+        .and_then(|layers: Vec<(PathBuf, Option<Format>)>| {
+            let mut layers = layers.into_iter();
+            let (first_path, first_format) = layers.next().ok_or(Error::MissingConfig)?;
+            let mut merged = load_layer(first_path, first_format)?;
+            for (path, format) in layers {
+                merged.merge(load_layer(path, format)?);
+            }
+            Ok(merged)
         })
-        .and_then(|config_file| Ok(Config::from_file(config_file)?))
 }
 
-fn embed_config_value_impl(name: LitStr) -> Result<TokenStream, syn::Error> {
-    use toml::Value;
+/// The fixed-width integer types `EmbedConfig` knows how to narrow a TOML
+/// integer into, with compile-time range checking.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum IntWidth {
+    I8,
+    I16,
+    I32,
+    I64,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+}
+
+impl IntWidth {
+    fn from_ident(ident: &str) -> Option<Self> {
+        Some(match ident {
+            "i8" => Self::I8,
+            "i16" => Self::I16,
+            "i32" => Self::I32,
+            "i64" => Self::I64,
+            "isize" => Self::Isize,
+            "u8" => Self::U8,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "usize" => Self::Usize,
+            _ => return None,
+        })
+    }
+
+    /// `isize`/`usize` are checked against `i32`/`u32` bounds rather than
+    /// the proc-macro host's 64-bit pointer width: this crate's whole point
+    /// is embedding constants for MCU targets, where `isize`/`usize` are
+    /// commonly 16 or 32 bits. Checking against the host width would let a
+    /// value like `3_000_000_000` pass here and silently overflow `isize`/
+    /// `usize` on the real (32-bit) target.
+    fn bounds(self) -> (i128, i128) {
+        match self {
+            Self::I8 => (i8::MIN as i128, i8::MAX as i128),
+            Self::I16 => (i16::MIN as i128, i16::MAX as i128),
+            Self::I32 | Self::Isize => (i32::MIN as i128, i32::MAX as i128),
+            Self::I64 => (i64::MIN as i128, i64::MAX as i128),
+            Self::U8 => (u8::MIN as i128, u8::MAX as i128),
+            Self::U16 => (u16::MIN as i128, u16::MAX as i128),
+            Self::U32 | Self::Usize => (u32::MIN as i128, u32::MAX as i128),
+            Self::U64 => (u64::MIN as i128, u64::MAX as i128),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::Isize => "isize",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::Usize => "usize",
+        }
+    }
+
+    fn literal(self, v: i64) -> Literal {
+        match self {
+            Self::I8 => Literal::i8_suffixed(v as i8),
+            Self::I16 => Literal::i16_suffixed(v as i16),
+            Self::I32 => Literal::i32_suffixed(v as i32),
+            Self::I64 => Literal::i64_suffixed(v),
+            Self::Isize => Literal::isize_suffixed(v as isize),
+            Self::U8 => Literal::u8_suffixed(v as u8),
+            Self::U16 => Literal::u16_suffixed(v as u16),
+            Self::U32 => Literal::u32_suffixed(v as u32),
+            Self::U64 => Literal::u64_suffixed(v as u64),
+            Self::Usize => Literal::usize_suffixed(v as usize),
+        }
+    }
+}
+
+fn check_int_range(v: i64, width: IntWidth, span: Span) -> Result<(), syn::Error> {
+    let (min, max) = width.bounds();
+    if (min..=max).contains(&(v as i128)) {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            span,
+            format!(
+                "{v} does not fit in `{}` (expected {min}..={max})",
+                width.name()
+            ),
+        ))
+    }
+}
+
+fn check_f32_round_trip(v: f64, span: Span) -> Result<(), syn::Error> {
+    if (v as f32) as f64 == v {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            span,
+            format!("{v} cannot be represented as `f32` without loss of precision"),
+        ))
+    }
+}
+
+fn check_int_to_f32_round_trip(v: i64, span: Span) -> Result<(), syn::Error> {
+    // Compare in `i128`: casting back to `i64` would saturate, so e.g.
+    // `i64::MAX` (which rounds up to 2^63) would wrongly compare equal.
+    if (v as f32) as i128 == v as i128 {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            span,
+            format!("{v} cannot be represented as `f32` without loss of precision"),
+        ))
+    }
+}
+
+fn check_int_to_f64_round_trip(v: i64, span: Span) -> Result<(), syn::Error> {
+    if (v as f64) as i128 == v as i128 {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            span,
+            format!("{v} cannot be represented as `f64` without loss of precision"),
+        ))
+    }
+}
+
+/// A leaf type `EmbedConfig` (and `embed_config_value_as!`) can resolve a
+/// TOML scalar into directly, without going through the widest native type.
+#[derive(Clone, Copy)]
+enum ScalarKind {
+    Bool,
+    Str,
+    Int(IntWidth),
+    F32,
+    F64,
+}
+
+impl ScalarKind {
+    fn from_ident(ident: &str) -> Option<Self> {
+        match ident {
+            "bool" => Some(Self::Bool),
+            "f32" => Some(Self::F32),
+            "f64" => Some(Self::F64),
+            _ => IntWidth::from_ident(ident).map(Self::Int),
+        }
+    }
+}
+
+/// Recognizes `bool`, `&'static str`, the fixed-width integers, `f32`/`f64`.
+/// Anything else is assumed to be a nested `#[derive(EmbedConfig)]` struct.
+fn scalar_kind_of(ty: &Type) -> Option<ScalarKind> {
+    if let Type::Reference(r) = ty {
+        let is_static = r.lifetime.as_ref().is_some_and(|lt| lt.ident == "static");
+        return match &*r.elem {
+            Type::Path(p) if is_static && p.path.is_ident("str") => Some(ScalarKind::Str),
+            _ => None,
+        };
+    }
+    let Type::Path(p) = ty else { return None };
+    ScalarKind::from_ident(&p.path.segments.last()?.ident.to_string())
+}
+
+fn toml_value_kind_name(value: &toml::Value) -> &'static str {
+    match value {
+        toml::Value::String(_) => "a string",
+        toml::Value::Integer(_) => "an integer",
+        toml::Value::Float(_) => "a float",
+        toml::Value::Boolean(_) => "a boolean",
+        toml::Value::Datetime(_) => "a datetime",
+        toml::Value::Array(_) => "an array",
+        toml::Value::Table(_) => "a table",
+    }
+}
+
+/// Converts a resolved `toml::Value` into a literal of exactly `kind`,
+/// range-checking integer narrowing and float precision along the way.
+/// Errors are raised at `span` (the target type's span for
+/// `embed_config_value_as!`, the derive's call site for a derived struct,
+/// whose errors name the field path instead) and describe the expected Rust
+/// type next to what the config actually held.
+fn scalar_literal(
+    kind: ScalarKind,
+    value: toml::Value,
+    ty_name: &str,
+    span: Span,
+) -> Result<TokenStream, syn::Error> {
+    let mismatch = |value: &toml::Value| {
+        syn::Error::new(
+            span,
+            format!(
+                "expected a value assignable to `{ty_name}`, found {} in the config",
+                toml_value_kind_name(value)
+            ),
+        )
+    };
+    match kind {
+        ScalarKind::Bool => match value {
+            toml::Value::Boolean(v) => Ok(quote! { #v }),
+            other => Err(mismatch(&other)),
+        },
+        ScalarKind::Str => match value {
+            toml::Value::String(v) => Ok(quote! { #v }),
+            other => Err(mismatch(&other)),
+        },
+        ScalarKind::Int(width) => match value {
+            toml::Value::Integer(v) => {
+                check_int_range(v, width, span)?;
+                let lit = width.literal(v);
+                Ok(quote! { #lit })
+            }
+            other => Err(mismatch(&other)),
+        },
+        ScalarKind::F64 => match value {
+            toml::Value::Float(v) => Ok(quote! { #v }),
+            toml::Value::Integer(v) => {
+                check_int_to_f64_round_trip(v, span)?;
+                let v = v as f64;
+                Ok(quote! { #v })
+            }
+            other => Err(mismatch(&other)),
+        },
+        ScalarKind::F32 => match value {
+            toml::Value::Float(v) => {
+                check_f32_round_trip(v, span)?;
+                let v = v as f32;
+                Ok(quote! { #v })
+            }
+            toml::Value::Integer(v) => {
+                check_int_to_f32_round_trip(v, span)?;
+                let v = v as f32;
+                Ok(quote! { #v })
+            }
+            other => Err(mismatch(&other)),
+        },
+    }
+}
+
+fn field_path(base: &str, field: &str) -> String {
+    if base.is_empty() {
+        field.to_string()
+    } else {
+        format!("{base}.{field}")
+    }
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(p) = ty else { return None };
+    let segment = p.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(t) => Some(t),
+        _ => None,
+    })
+}
+
+fn build_field_tokens(
+    cfg: &Config,
+    ty: &Type,
+    path: String,
+    span: Span,
+) -> Result<TokenStream, syn::Error> {
+    if let Some(inner) = option_inner(ty) {
+        if scalar_kind_of(inner).is_none() {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "field `{path}`: `Option` is only supported for scalar fields, not nested \
+                     `EmbedConfig` types"
+                ),
+            ));
+        }
+        return match cfg.resolve_field(&path) {
+            Ok(_) => {
+                let value = build_field_tokens(cfg, inner, path, span)?;
+                Ok(quote! { Some(#value) })
+            }
+            Err(ConfigError::MissingField(_)) => Ok(quote! { None }),
+            Err(e) => Err(syn::Error::new(span, format!("resolving `{path}`: {e}"))),
+        };
+    }
 
+    if let Some(kind) = scalar_kind_of(ty) {
+        let value = cfg
+            .resolve_field(&path)
+            .map_err(|e| syn::Error::new(span, format!("resolving `{path}`: {e}")))?;
+        return scalar_literal(kind, value, &quote!(#ty).to_string(), span)
+            .map_err(|e| syn::Error::new(e.span(), format!("field `{path}`: {e}")));
+    }
+
+    // Anything else is assumed to be another `#[derive(EmbedConfig)]` struct.
+    // It resolves its own subtree independently, by re-reading the config
+    // file from its own `#[embed_config(path = "...")]` attribute, so we
+    // just reference its already-generated constant; if it isn't actually
+    // an `EmbedConfig` type, rustc reports the missing `EMBEDDED` const at
+    // this span.
+    Ok(quote! { <#ty>::EMBEDDED })
+}
+
+fn build_struct_tokens(
+    cfg: &Config,
+    struct_name: &Ident,
+    fields: &[(Ident, Type)],
+    base: &str,
+    span: Span,
+) -> Result<TokenStream, syn::Error> {
+    let inits = fields
+        .iter()
+        .map(|(name, ty)| {
+            let value = build_field_tokens(cfg, ty, field_path(base, &name.to_string()), span)?;
+            Ok(quote! { #name: #value })
+        })
+        .collect::<Result<Vec<_>, syn::Error>>()?;
+    Ok(quote! { #struct_name { #(#inits),* } })
+}
+
+/// Reads `#[embed_config(path = "...")]` off the struct, defaulting to the
+/// config root when absent.
+fn parse_embed_config_path(attrs: &[Attribute]) -> Result<String, syn::Error> {
+    for attr in attrs {
+        if !attr.path().is_ident("embed_config") {
+            continue;
+        }
+        let mut path = None;
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("path") {
+                path = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported `embed_config` attribute key"))
+            }
+        })?;
+        if let Some(path) = path {
+            return Ok(path);
+        }
+    }
+    Ok(String::new())
+}
+
+fn derive_embed_config_impl(input: DeriveInput) -> Result<TokenStream, syn::Error> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input,
+            "`EmbedConfig` can only be derived for structs with named fields",
+        ));
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input,
+            "`EmbedConfig` can only be derived for structs with named fields",
+        ));
+    };
+
+    let fields: Vec<(Ident, Type)> = named
+        .named
+        .iter()
+        .map(|f| (f.ident.clone().expect("named field"), f.ty.clone()))
+        .collect();
+    let base_path = parse_embed_config_path(&input.attrs)?;
+    let struct_name = input.ident.clone();
+
+    let cfg = load_embed_config().map_err(|e| syn::Error::new(Span::call_site(), e.to_string()))?;
+    let body = build_struct_tokens(&cfg, &struct_name, &fields, &base_path, Span::call_site())?;
+
+    Ok(quote! {
+        impl #struct_name {
+            /// The fully materialized configuration value, resolved and
+            /// type-checked at compile time from the embedded config file.
+            pub const EMBEDDED: #struct_name = #body;
+        }
+    })
+}
+
+fn toml_discriminant_name(value: &toml::Value) -> &'static str {
+    match value {
+        toml::Value::Boolean(_) => "bool",
+        toml::Value::String(_) => "string",
+        toml::Value::Float(_) => "float",
+        toml::Value::Integer(_) => "integer",
+        toml::Value::Array(_) => "array",
+        toml::Value::Table(_) => "table",
+        toml::Value::Datetime(_) => "datetime",
+    }
+}
+
+/// Emits a homogeneous array as a `&'static [T]` slice literal, recursing
+/// for nested arrays (`&[&[T]]`). Mixed-type arrays are rejected at
+/// macro-expansion time, naming the first offending index.
+fn emit_array(items: Vec<toml::Value>, span: Span) -> Result<TokenStream, syn::Error> {
+    let Some(first) = items.first() else {
+        return Err(syn::Error::new(
+            span,
+            "cannot embed an empty array: its element type can't be inferred",
+        ));
+    };
+    let expected = toml_discriminant_name(first);
+    if let Some((idx, found)) = items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| (idx, toml_discriminant_name(item)))
+        .find(|(_, found)| *found != expected)
+    {
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "array elements must share a type: index 0 is {expected}, but index {idx} is {found}"
+            ),
+        ));
+    }
+    let elems = items
+        .into_iter()
+        .map(|item| emit_value(item, span))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(quote! { &[#(#elems),*] })
+}
+
+/// Emits a table as a tuple of its values, in key order, letting each
+/// field keep its own type. Reaching a single leaf inside a table is still
+/// possible as before, by dotting further into the field path.
+fn emit_table(
+    table: toml::map::Map<String, toml::Value>,
+    span: Span,
+) -> Result<TokenStream, syn::Error> {
+    let elems = table
+        .into_iter()
+        .map(|(_, value)| emit_value(value, span))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let [elem] = elems.as_slice() {
+        // `(#elem)` is a parenthesized expression, not a 1-tuple; force the
+        // trailing comma so a single-field table still comes out as a tuple.
+        return Ok(quote! { (#elem,) });
+    }
+    Ok(quote! { (#(#elems),*) })
+}
+
+fn emit_value(value: toml::Value, span: Span) -> Result<TokenStream, syn::Error> {
+    match value {
+        toml::Value::Boolean(v) => Ok(quote! { #v }),
+        toml::Value::String(v) => Ok(quote! { #v }),
+        toml::Value::Float(v) => Ok(quote! { #v }),
+        toml::Value::Integer(v) => Ok(quote! { #v }),
+        toml::Value::Array(items) => emit_array(items, span),
+        toml::Value::Table(table) => emit_table(table, span),
+        toml::Value::Datetime(_) => {
+            Err(syn::Error::new(span, "datetime values cannot be embedded"))
+        }
+    }
+}
+
+fn embed_config_value_impl(name: LitStr) -> Result<TokenStream, syn::Error> {
     let cfg = load_embed_config().map_err(|e| syn::Error::new(Span::call_site(), e.to_string()))?;
     let val = cfg
         .resolve_field(&name.value())
         .map_err(|e| syn::Error::new(name.span(), e.to_string()))?;
-    match val {
-        Value::Boolean(v) => Ok(quote! { #v }),
-        Value::String(v) => Ok(quote! { #v }),
-        Value::Float(v) => Ok(quote! { #v }),
-        Value::Integer(v) => Ok(quote! { #v }),
-        _ => Err(syn::Error::new(
-            name.span(),
-            "resulted in unsupported return type",
-        )),
-    }
+    emit_value(val, name.span())
+}
+
+fn embed_config_value_as_impl(args: EmbedConfigValueAs) -> Result<TokenStream, syn::Error> {
+    use syn::spanned::Spanned;
+
+    let cfg = load_embed_config().map_err(|e| syn::Error::new(Span::call_site(), e.to_string()))?;
+    let val = cfg
+        .resolve_field(&args.name.value())
+        .map_err(|e| syn::Error::new(args.name.span(), e.to_string()))?;
+    let ty = &args.ty;
+    let ty_span = ty.span();
+    let kind = scalar_kind_of(ty).ok_or_else(|| {
+        syn::Error::new(
+            ty_span,
+            "embed_config_value_as! only supports bool, &'static str, the fixed-width integers, and f32/f64",
+        )
+    })?;
+    scalar_literal(kind, val, &quote!(#ty).to_string(), ty_span)
 }